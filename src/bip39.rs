@@ -0,0 +1,91 @@
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+/// Supported entropy sizes, in bits, smallest to largest.
+const ENT_SIZES: &[u32] = &[128, 160, 192, 224, 256];
+
+/// Number of mnemonic words produced for `ent_bits` bits of entropy.
+/// words = (ENT + ENT/32) / 11
+fn word_count(ent_bits: u32) -> u32 {
+    (ent_bits + ent_bits / 32) / 11
+}
+
+/// Pick the smallest supported entropy size whose mnemonic has at least
+/// `target_words` words.
+pub fn entropy_bits_for_words(target_words: u32) -> Result<u32, &'static str> {
+    ENT_SIZES
+        .iter()
+        .copied()
+        .find(|&ent| word_count(ent) >= target_words)
+        .ok_or("Requested length exceeds the largest supported BIP39 mnemonic (24 words)")
+}
+
+/// Generate a checksummed BIP39 mnemonic for `ent_bits` bits of entropy
+/// (one of 128/160/192/224/256), drawn from `rng`.
+pub fn generate(ent_bits: u32, words: &[&str], rng: &mut impl RngCore) -> String {
+    let mut entropy = vec![0u8; (ent_bits / 8) as usize];
+    rng.fill_bytes(&mut entropy);
+    mnemonic(&entropy, words)
+}
+
+/// Build the checksummed mnemonic for a given entropy buffer: hash it with
+/// SHA-256, append the leading ENT/32 bits of the hash as a checksum, then
+/// slice the concatenated bitstream into consecutive 11-bit word indices.
+fn mnemonic(entropy: &[u8], words: &[&str]) -> String {
+    let ent_bits = entropy.len() as u32 * 8;
+    let checksum_bits = ent_bits / 32;
+
+    let hash = Sha256::digest(entropy);
+    let mut bits = entropy.to_vec();
+    bits.push(hash[0]);
+
+    (0..ent_bits + checksum_bits)
+        .step_by(11)
+        .map(|start| {
+            let idx = (0..11).fold(0usize, |acc, i| {
+                let bit = start + i;
+                let byte = bits[(bit / 8) as usize];
+                let b = (byte >> (7 - bit % 8)) & 1;
+                (acc << 1) | b as usize
+            });
+            words[idx]
+        })
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wordlist() -> Vec<&'static str> {
+        crate::DICTIONARIES
+            .iter()
+            .find(|d| d.name == "bip39")
+            .unwrap()
+            .data
+            .lines()
+            .collect()
+    }
+
+    // Standard BIP39 known-answer test vectors.
+    // https://github.com/trezor/python-mnemonic/blob/master/vectors.json
+
+    #[test]
+    fn test_mnemonic_all_zero_entropy() {
+        let words = wordlist();
+        assert_eq!(
+            mnemonic(&[0u8; 16], &words),
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about"
+        );
+    }
+
+    #[test]
+    fn test_mnemonic_all_one_entropy() {
+        let words = wordlist();
+        assert_eq!(
+            mnemonic(&[0xffu8; 16], &words),
+            "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo wrong"
+        );
+    }
+}
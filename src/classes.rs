@@ -0,0 +1,124 @@
+use std::str::FromStr;
+
+/// Minimum per-class character counts requested via `--require-classes`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClassRequirement {
+    pub upper: u32,
+    pub lower: u32,
+    pub digit: u32,
+    pub symbol: u32,
+}
+
+impl ClassRequirement {
+    /// True if this requirement asks for nothing at all.
+    pub fn is_empty(&self) -> bool {
+        *self == ClassRequirement::default()
+    }
+}
+
+/// Parses specs like `U1L1D1S1` (one upper, one lower, one digit, one
+/// symbol) or `U2D1` (two upper, one digit). A class letter with no count
+/// defaults to requiring one.
+impl FromStr for ClassRequirement {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut req = ClassRequirement::default();
+        let mut chars = s.chars().peekable();
+
+        while let Some(class) = chars.next() {
+            let mut digits = String::new();
+            while let Some(c) = chars.peek() {
+                if c.is_ascii_digit() {
+                    digits.push(*c);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            let count: u32 = if digits.is_empty() {
+                1
+            } else {
+                digits.parse().map_err(|_| format!("Invalid count in {:?}", s))?
+            };
+
+            match class.to_ascii_uppercase() {
+                'U' => req.upper += count,
+                'L' => req.lower += count,
+                'D' => req.digit += count,
+                'S' => req.symbol += count,
+                _ => return Err(format!("Unknown character class {:?}", class)),
+            }
+        }
+
+        Ok(req)
+    }
+}
+
+/// Counts of each character class present in a candidate password.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CharDistro {
+    pub upper: u32,
+    pub lower: u32,
+    pub digit: u32,
+    pub symbol: u32,
+}
+
+impl CharDistro {
+    /// Tally the character classes present in `s`.
+    pub fn count(s: &str) -> Self {
+        let mut distro = CharDistro::default();
+
+        for c in s.chars() {
+            if c.is_ascii_uppercase() {
+                distro.upper += 1;
+            } else if c.is_ascii_lowercase() {
+                distro.lower += 1;
+            } else if c.is_ascii_digit() {
+                distro.digit += 1;
+            } else if c.is_ascii_graphic() {
+                distro.symbol += 1;
+            }
+        }
+
+        distro
+    }
+
+    /// True if this distribution meets or exceeds every minimum in `req`.
+    pub fn satisfies(&self, req: &ClassRequirement) -> bool {
+        self.upper >= req.upper
+            && self.lower >= req.lower
+            && self.digit >= req.digit
+            && self.symbol >= req.symbol
+    }
+}
+
+#[test]
+fn test_parse_class_requirement() {
+    assert_eq!(
+        "U1L1D1S1".parse::<ClassRequirement>().unwrap(),
+        ClassRequirement {
+            upper: 1,
+            lower: 1,
+            digit: 1,
+            symbol: 1,
+        }
+    );
+    assert_eq!(
+        "ULD2".parse::<ClassRequirement>().unwrap(),
+        ClassRequirement {
+            upper: 1,
+            lower: 1,
+            digit: 2,
+            symbol: 0,
+        }
+    );
+    assert!("X".parse::<ClassRequirement>().is_err());
+}
+
+#[test]
+fn test_char_distro_satisfies() {
+    let req = "U1L1D1S1".parse::<ClassRequirement>().unwrap();
+    assert!(CharDistro::count("aB3!").satisfies(&req));
+    assert!(!CharDistro::count("abc3").satisfies(&req));
+}
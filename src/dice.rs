@@ -1,6 +1,9 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io::{self, Read};
+use std::rc::Rc;
+
 use ibig::UBig;
-use rand::distributions::{Distribution, Uniform};
-use rand::rngs::OsRng;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum FastDiceRollerKind {
@@ -10,13 +13,45 @@ enum FastDiceRollerKind {
     NonPowerOfTwo,
 }
 
+/// Where `FastDiceRoller` gets its raw 0..modulus rolls from.
+#[derive(Debug, Clone)]
+pub enum DiceSource {
+    /// Prompt interactively for each roll via rustyline.
+    Interactive,
+    /// Consume pre-recorded rolls, e.g. read from stdin. Shared (not
+    /// cloned) across recursive rollers so they draw from one sequence.
+    Stdin(Rc<RefCell<VecDeque<u32>>>),
+}
+
+impl DiceSource {
+    /// Build a stdin-backed source from a list of already-parsed rolls.
+    pub fn from_rolls(rolls: Vec<u32>) -> Self {
+        DiceSource::Stdin(Rc::new(RefCell::new(rolls.into_iter().collect())))
+    }
+}
+
+/// Parse whitespace/newline separated roll values out of `r`, for use with
+/// `DiceSource::from_rolls`. Does not validate rolls against a modulus;
+/// that happens as each one is consumed.
+pub fn read_rolls<R: Read>(mut r: R) -> io::Result<Vec<u32>> {
+    let mut input = String::new();
+    r.read_to_string(&mut input)?;
+    input
+        .split_whitespace()
+        .map(|tok| {
+            tok.parse()
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("Invalid dice roll {:?}", tok)))
+        })
+        .collect()
+}
+
 #[derive(Debug, Clone)]
 pub struct FastDiceRoller {
     modulus: u32,
     modulus_bits: f64,
     max_inclusive: UBig,
     kind: FastDiceRollerKind,
-    real_dice: bool,
+    source: DiceSource,
 }
 
 /// A reformulation of Lumbroso's Fast Dice Roller described in 2009's
@@ -30,7 +65,7 @@ pub struct FastDiceRoller {
 /// https://twitter.com/gro_tsen/status/1386448258176884737
 impl FastDiceRoller {
     /// Generate values from 0..=max_inclusive from a uniform random source 0..modulus
-    pub fn new(max_inclusive: UBig, modulus: u32, real_dice: bool) -> Self {
+    pub fn new(max_inclusive: UBig, modulus: u32, source: DiceSource) -> Self {
         let modulus_bits = (modulus as f64).log2();
 
         let kind = if max_inclusive == UBig::from(0u32) {
@@ -48,15 +83,14 @@ impl FastDiceRoller {
             modulus_bits,
             max_inclusive,
             kind,
-            real_dice,
+            source,
         }
     }
 
     fn read_dice(&self) -> u32 {
-        if self.real_dice {
-            self.read_real_dice()
-        } else {
-            Uniform::from(0..self.modulus).sample(&mut OsRng)
+        match &self.source {
+            DiceSource::Interactive => self.read_real_dice(),
+            DiceSource::Stdin(rolls) => self.read_stdin_dice(rolls),
         }
     }
 
@@ -79,6 +113,20 @@ impl FastDiceRoller {
         }
     }
 
+    fn read_stdin_dice(&self, rolls: &Rc<RefCell<VecDeque<u32>>>) -> u32 {
+        loop {
+            let num = rolls.borrow_mut().pop_front().unwrap_or_else(|| {
+                eprintln!("Ran out of dice rolls on stdin before generating enough output.");
+                std::process::exit(1);
+            });
+
+            if num > 0 && num <= self.modulus {
+                return num - 1;
+            }
+            eprintln!("{} out of range 1-{}", num, self.modulus);
+        }
+    }
+
     fn power_of_two(&self) -> UBig {
         let mut x = UBig::from(1u32);
         let mut y = UBig::from(0u32);
@@ -121,7 +169,7 @@ impl FastDiceRoller {
             }
         } else {
             let cx = (&self.max_inclusive / self.modulus) + 1;
-            let mut cx_roller = FastDiceRoller::new(&cx - 1, self.modulus, self.real_dice);
+            let mut cx_roller = FastDiceRoller::new(&cx - 1, self.modulus, self.source.clone());
             loop {
                 let mut ret: UBig = &cx * self.read_dice();
                 ret += cx_roller.next().unwrap();
@@ -145,3 +193,47 @@ impl Iterator for FastDiceRoller {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roller(max_inclusive: u32, modulus: u32, rolls: &[u32]) -> FastDiceRoller {
+        FastDiceRoller::new(
+            UBig::from(max_inclusive),
+            modulus,
+            DiceSource::from_rolls(rolls.to_vec()),
+        )
+    }
+
+    #[test]
+    fn test_power_of_two() {
+        // modulus is a power of two (an 8-sided die), consuming exactly
+        // one roll's 3 bits.
+        let mut r = roller(5, 8, &[7]);
+        assert_eq!(r.next(), Some(UBig::from(3u32)));
+    }
+
+    #[test]
+    fn test_non_power_of_two_small() {
+        // modulus (6) isn't a power of two, and max_inclusive < modulus.
+        let mut r = roller(3, 6, &[3]);
+        assert_eq!(r.next(), Some(UBig::from(2u32)));
+    }
+
+    #[test]
+    fn test_non_power_of_two_large() {
+        // max_inclusive >= modulus recurses into a smaller FastDiceRoller
+        // sharing the same roll stream.
+        let mut r = roller(20, 6, &[2, 3]);
+        assert_eq!(r.next(), Some(UBig::from(6u32)));
+    }
+
+    #[test]
+    fn test_stdin_rolls_shared_across_recursion() {
+        // Both the outer roller and its internal cx_roller must draw from
+        // the same stdin sequence, in order.
+        let mut r = roller(35, 6, &[3, 5]);
+        assert_eq!(r.next(), Some(UBig::from(16u32)));
+    }
+}
@@ -1,17 +1,24 @@
 #![allow(clippy::uninlined_format_args)]
 
 use std::collections::HashSet;
+use std::io::IsTerminal;
 use std::path::PathBuf;
 
+use arboard::Clipboard;
 use clap::{builder::PossibleValuesParser, Parser};
 use eyre::{ensure, eyre, Result, WrapErr};
 use ibig::UBig;
 use rand::distributions::{Distribution, Uniform};
 use rand::rngs::OsRng;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
 use read_restrict::read_to_string;
 
+mod bip39;
+mod classes;
 mod dice;
-use dice::FastDiceRoller;
+use classes::{CharDistro, ClassRequirement};
+use dice::{DiceSource, FastDiceRoller};
 
 #[derive(Debug)]
 struct PassFormat {
@@ -50,8 +57,20 @@ const DICTIONARIES: &[PassFormat] = defdicts! {
     "hex"               + ""  = "Hexadecimal"
     "printable"         + ""  = "Mixed-case a-z 0-9 plus standard ASCII symbols"
     "koremutake"        + " " = "A \"way to express any large number as a sequence of syllables\"\n  https://shorl.com/koremutake.php"
+    "rfc1751"           + " " = "RFC1751 / S/KEY word list - short, phonetically distinct words for reading aloud\n  https://www.rfc-editor.org/rfc/rfc1751"
+    "bip39"             + " " = "BIP39 English word list - selecting it switches to checksummed mnemonic mode\n  https://github.com/bitcoin/bips/blob/master/bip-0039/english.txt"
 };
 
+#[test]
+fn test_bip39_wordlist_length() {
+    let bip39 = DICTIONARIES.iter().find(|d| d.name == "bip39").unwrap();
+    assert_eq!(
+        bip39.data.lines().count(),
+        2048,
+        "BIP39 wordlist must have exactly 2048 entries, as relied on by the 11-bit word indices in bip39::mnemonic"
+    );
+}
+
 #[test]
 fn test_dictionaries() {
     for dict in DICTIONARIES.iter() {
@@ -100,6 +119,159 @@ fn crack_times(combinations: &UBig) -> Vec<(&'static str, f64)> {
     ]
 }
 
+/// Number of samples `estimate_acceptance` draws to estimate a
+/// --require-classes policy's acceptance rate for the verbose entropy
+/// report.
+const ACCEPTANCE_TRIALS: u32 = 20_000;
+
+/// Upper bound on rejection-sampling attempts per password before giving up
+/// with an error, so a --require-classes policy that's feasible but
+/// vanishingly rare fails loudly instead of spinning forever.
+const MAX_REJECTION_ATTEMPTS: u32 = 10_000_000;
+
+/// Estimate the fraction of randomly generated passwords that satisfy `req`,
+/// by brute-force sampling. Used only to account for the entropy rejection
+/// sampling throws away; whether the policy is satisfiable at all is decided
+/// separately by `class_requirement_feasible`, since sampling noise is the
+/// wrong tool for a yes/no answer.
+fn estimate_acceptance(
+    dict: &[&str],
+    length: u32,
+    separator: &str,
+    req: &ClassRequirement,
+    trials: u32,
+    rng: &mut dyn RngCore,
+) -> f64 {
+    let sampler = Uniform::from(0..dict.len());
+    let successes = (0..trials)
+        .filter(|_| {
+            let candidate = sampler
+                .sample_iter(&mut *rng)
+                .take(length as usize)
+                .map(|i| dict[i])
+                .collect::<Vec<&str>>()
+                .join(separator);
+            CharDistro::count(&candidate).satisfies(req)
+        })
+        .count();
+
+    successes as f64 / trials as f64
+}
+
+/// True if `req` can possibly be satisfied by *some* run of `length` words
+/// from `dict` joined by `separator`. An exact check, unlike
+/// `estimate_acceptance`: a policy satisfiable only by a vanishingly rare
+/// combination must still be reported as feasible, rather than flipping
+/// between "impossible" and "fine" depending on what a fixed number of
+/// Monte-Carlo trials happened to turn up.
+///
+/// Maximizing each class independently (one word supplying the best upper
+/// count, a *different* word supplying the best lower count, etc.) would
+/// over-report feasibility: a dictionary of "A1" (upper, no lower) and "a#"
+/// (lower, no upper) can never produce upper>=2 and lower>=2 in two words,
+/// even though each class alone is reachable. Instead, subtract the fixed
+/// contribution of the `length - 1` separators from `req`, then do a BFS
+/// over the per-word class vectors to find the minimum number of whole
+/// words needed to cover what's left, and compare that to `length`.
+fn class_requirement_feasible(
+    dict: &[&str],
+    length: u32,
+    separator: &str,
+    req: &ClassRequirement,
+) -> bool {
+    if req.is_empty() {
+        return true;
+    }
+
+    let separators = length.saturating_sub(1);
+    let sep = CharDistro::count(separator);
+    let need = ClassRequirement {
+        upper: req.upper.saturating_sub(sep.upper.saturating_mul(separators)),
+        lower: req.lower.saturating_sub(sep.lower.saturating_mul(separators)),
+        digit: req.digit.saturating_sub(sep.digit.saturating_mul(separators)),
+        symbol: req.symbol.saturating_sub(sep.symbol.saturating_mul(separators)),
+    };
+
+    let start = (need.upper, need.lower, need.digit, need.symbol);
+    if start == (0, 0, 0, 0) {
+        return true;
+    }
+
+    // Clamp each word's contribution to what's still needed per class:
+    // drawing more of a class than required never helps reach a different
+    // one, and clamping keeps the BFS state space bounded by `need` rather
+    // than by the actual (possibly huge) counts in long dictionary entries.
+    let mut words: Vec<(u32, u32, u32, u32)> = dict
+        .iter()
+        .map(|w| {
+            let c = CharDistro::count(w);
+            (
+                c.upper.min(need.upper),
+                c.lower.min(need.lower),
+                c.digit.min(need.digit),
+                c.symbol.min(need.symbol),
+            )
+        })
+        .collect();
+    words.sort_unstable();
+    words.dedup();
+    words.retain(|&v| v != (0, 0, 0, 0));
+
+    if words.is_empty() {
+        return false;
+    }
+
+    let mut visited = HashSet::from([start]);
+    let mut frontier = vec![start];
+    for _ in 0..length {
+        let mut next = Vec::new();
+        for state in frontier {
+            for &(wu, wl, wd, ws) in &words {
+                let candidate = (
+                    state.0.saturating_sub(wu),
+                    state.1.saturating_sub(wl),
+                    state.2.saturating_sub(wd),
+                    state.3.saturating_sub(ws),
+                );
+                if candidate == (0, 0, 0, 0) {
+                    return true;
+                }
+                if visited.insert(candidate) {
+                    next.push(candidate);
+                }
+            }
+        }
+        if next.is_empty() {
+            return false;
+        }
+        frontier = next;
+    }
+
+    false
+}
+
+#[test]
+fn test_class_requirement_feasible() {
+    let dict = ["abc", "def"];
+    let req: ClassRequirement = "S1".parse().unwrap();
+
+    assert!(!class_requirement_feasible(&dict, 3, "", &req));
+    assert!(class_requirement_feasible(&dict, 3, "-", &req));
+}
+
+#[test]
+fn test_class_requirement_feasible_rejects_cross_class_combination() {
+    // "A1" can only ever supply the upper class, "a#" only the lower class,
+    // so two words can never have upper>=2 *and* lower>=2 at once, even
+    // though upper>=2 alone and lower>=2 alone are each individually
+    // reachable.
+    let dict = ["A1", "a#"];
+    let req: ClassRequirement = "U2L2".parse().unwrap();
+
+    assert!(!class_requirement_feasible(&dict, 2, "", &req));
+    assert!(class_requirement_feasible(&dict, 4, "", &req));
+}
+
 fn password_strength(entropy: u32) -> &'static str {
     const THRESHOLDS: &[(u32, &str)] = &[
         (29, "very weak"),
@@ -149,6 +321,60 @@ fn human_duration(secs: f64) -> String {
     "trillions of years".to_string()
 }
 
+fn parse_seed(arg: &str) -> Result<[u8; 32], String> {
+    let hex = arg.strip_prefix("0x").unwrap_or(arg);
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|s| u8::from_str_radix(s, 16).ok())
+                .ok_or_else(|| format!("{:?} is not valid hex", arg))
+        })
+        .collect::<Result<Vec<u8>, String>>()?;
+
+    let mut seed = [0u8; 32];
+    if bytes.len() != seed.len() {
+        return Err(format!(
+            "Seed must be exactly {} bytes ({} hex characters), got {}",
+            seed.len(),
+            seed.len() * 2,
+            bytes.len()
+        ));
+    }
+    seed.copy_from_slice(&bytes);
+    Ok(seed)
+}
+
+#[test]
+fn test_parse_seed() {
+    let zeroes = "00".repeat(32);
+    assert_eq!(parse_seed(&zeroes).unwrap(), [0u8; 32]);
+
+    let mut expected = [0u8; 32];
+    expected[0] = 0xab;
+    expected[31] = 0xff;
+    assert_eq!(
+        parse_seed(&format!("0xab{}ff", "00".repeat(30))).unwrap(),
+        expected
+    );
+}
+
+#[test]
+fn test_parse_seed_rejects_odd_length() {
+    assert!(parse_seed(&"00".repeat(32)[..63]).is_err());
+}
+
+#[test]
+fn test_parse_seed_rejects_non_hex() {
+    assert!(parse_seed(&("gg".to_string() + &"00".repeat(31))).is_err());
+}
+
+#[test]
+fn test_parse_seed_rejects_wrong_length() {
+    assert!(parse_seed("00").is_err());
+    assert!(parse_seed(&"00".repeat(33)).is_err());
+}
+
 fn parse_target_bits(arg: &str) -> Result<f64, &'static str> {
     match arg.parse::<f64>() {
         Ok(f) if f.is_finite() && (1.0..65535.0).contains(&f) => Ok(f),
@@ -176,6 +402,12 @@ struct Opt {
     #[arg(short, short_alias = 'n', long, default_value = "1")]
     count: u32,
 
+    /// Copy the generated password to the clipboard instead of printing it.
+    /// If --count is greater than 1, only the last password is copied; the
+    /// rest are still printed.
+    #[arg(short = 'C', long)]
+    clipboard: bool,
+
     /// Password strength target, 2^n
     #[arg(short, long, default_value_t = 72.0, value_parser = parse_target_bits)]
     bits: f64,
@@ -198,6 +430,13 @@ struct Opt {
     )]
     dictionary: String,
 
+    /// Require a minimum number of each character class, e.g. "U1L1D1S1" for
+    /// at least one upper-case, lower-case, digit and symbol. Regenerates
+    /// via rejection sampling until the requirement is met, failing with an
+    /// error after 10,000,000 rejected candidates for one password.
+    #[arg(long, value_name = "SPEC")]
+    require_classes: Option<ClassRequirement>,
+
     /// Manually use dice for randomness.
     #[arg(
         long,
@@ -206,6 +445,17 @@ struct Opt {
     )]
     dice: Option<u32>,
 
+    /// Read dice rolls from stdin instead of prompting interactively.
+    /// Implied when stdin isn't a terminal.
+    #[arg(long)]
+    dice_stdin: bool,
+
+    /// Seed the RNG for reproducible output (DANGEROUS: the result is only
+    /// as secret as the seed). Takes a 32-byte value as hex, e.g. "--seed
+    /// $(openssl rand -hex 32)".
+    #[arg(long, value_name = "HEX", value_parser = parse_seed)]
+    seed: Option<[u8; 32]>,
+
     /// Describe built-in dictionaries
     #[arg(short = 'D', long)]
     list_dictionaries: bool,
@@ -237,6 +487,82 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
+    if opts.dictionary == "bip39" {
+        ensure!(opts.file.is_none(), eyre!("--file cannot be combined with the bip39 dictionary"));
+        ensure!(opts.dice.is_none(), eyre!("--dice cannot be combined with the bip39 dictionary"));
+        ensure!(
+            opts.require_classes.is_none(),
+            eyre!("--require-classes cannot be combined with the bip39 dictionary")
+        );
+        ensure!(
+            opts.separator.is_none(),
+            eyre!("--separator cannot be combined with the bip39 dictionary (mnemonics are always space-separated)")
+        );
+
+        let words: Vec<&str> = DICTIONARIES
+            .iter()
+            .find(|d| d.name == "bip39")
+            .expect("Can't find dictionary")
+            .data
+            .lines()
+            .collect();
+
+        if opts.dump {
+            for word in &words {
+                println!("{}", word);
+            }
+            return Ok(());
+        }
+
+        let target_words = opts
+            .length
+            .unwrap_or((opts.bits / 11.0).ceil() as u32)
+            .max(1);
+        let ent_bits = bip39::entropy_bits_for_words(target_words).map_err(|e| eyre!(e))?;
+
+        if opts.verbose {
+            eprintln!("# {:>12}: bip39", "Dictionary");
+            eprintln!(
+                "# {:>12}: {:.2} bits ({})",
+                "Entropy",
+                ent_bits as f64,
+                password_strength(ent_bits)
+            );
+            eprintln!("#");
+        }
+
+        let mut rng: Box<dyn RngCore> = if let Some(seed) = opts.seed {
+            eprintln!("WARNING: Seeded output is only as secret as the seed.");
+            Box::new(ChaCha20Rng::from_seed(seed))
+        } else {
+            Box::new(OsRng)
+        };
+
+        let mut clipboard = if opts.clipboard {
+            Some(Clipboard::new().wrap_err("Failed to access clipboard")?)
+        } else {
+            None
+        };
+
+        for i in 0..opts.count {
+            let mnemonic = bip39::generate(ent_bits, &words, &mut rng);
+
+            if let Some(clipboard) = clipboard.as_mut() {
+                if i + 1 == opts.count {
+                    clipboard
+                        .set_text(mnemonic)
+                        .wrap_err("Failed to copy password to clipboard")?;
+                    eprintln!("Password copied to clipboard.");
+                    continue;
+                }
+            }
+
+            println!("{}", mnemonic);
+        }
+
+        return Ok(());
+    }
+
     if let Some(wl) = opts.file {
         wordlist = read_to_string(&wl, 1024 * 1024 * 128)
             .wrap_err_with(|| format!("Failed to read word list from {}", &wl.display()))?;
@@ -291,6 +617,33 @@ fn main() -> Result<()> {
         .unwrap_or((opts.bits / bits_per_word).ceil() as u32)
         .max(1);
 
+    let acceptance = match opts.require_classes {
+        Some(req) if !req.is_empty() => {
+            ensure!(
+                class_requirement_feasible(&dict, length, separator, &req),
+                eyre!(
+                    "--require-classes {:?} is impossible to satisfy from dictionary {:?}",
+                    opts.require_classes.unwrap(),
+                    opts.dictionary
+                )
+            );
+
+            let mut rng: Box<dyn RngCore> = match opts.seed {
+                Some(seed) => Box::new(ChaCha20Rng::from_seed(seed)),
+                None => Box::new(OsRng),
+            };
+            Some(estimate_acceptance(
+                &dict,
+                length,
+                separator,
+                &req,
+                ACCEPTANCE_TRIALS,
+                &mut *rng,
+            ))
+        }
+        _ => None,
+    };
+
     if opts.verbose {
         let combinations = UBig::from(dict.len()).pow(length as usize);
         let entropy = bits_per_word * length as f64;
@@ -307,6 +660,27 @@ fn main() -> Result<()> {
             entropy,
             password_strength(entropy as u32)
         );
+        if let Some(p) = acceptance {
+            if p > 0.0 {
+                let bits_lost = -p.log2();
+                let effective = entropy - bits_lost;
+                eprintln!(
+                    "# {:>12}: {:.2} bits lost to --require-classes rejection sampling",
+                    "Rejection", bits_lost
+                );
+                eprintln!(
+                    "# {:>12}: {:.2} bits ({})",
+                    "Effective",
+                    effective,
+                    password_strength(effective as u32)
+                );
+            } else {
+                eprintln!(
+                    "# {:>12}: acceptance rate too low to measure over {} trials; effective entropy estimate unavailable",
+                    "Rejection", ACCEPTANCE_TRIALS
+                );
+            }
+        }
         eprintln!("#");
         eprintln!("# Attack time estimate:");
         for (attack, duration) in crack_times(&combinations) {
@@ -315,10 +689,22 @@ fn main() -> Result<()> {
         eprintln!("#");
     }
 
+    ensure!(
+        opts.dice.is_none() || opts.seed.is_none(),
+        eyre!("--dice and --seed cannot be combined; --dice already supplies the randomness, so --seed would be silently ignored")
+    );
+
     let mut random_words: Box<dyn Iterator<Item = &str>> = if let Some(sides) = opts.dice {
         eprintln!("WARNING: Dice support is experimental.");
+        let source = if opts.dice_stdin || !std::io::stdin().is_terminal() {
+            let rolls =
+                dice::read_rolls(std::io::stdin()).wrap_err("Failed to read dice rolls from stdin")?;
+            DiceSource::from_rolls(rolls)
+        } else {
+            DiceSource::Interactive
+        };
         let dice =
-            FastDiceRoller::new(UBig::from(dict.len()).pow(length as usize) - 1, sides, true);
+            FastDiceRoller::new(UBig::from(dict.len()).pow(length as usize) - 1, sides, source);
         Box::new(dice.flat_map(|roll| {
             let dict = &dict;
             (0..length).rev().map(move |i| {
@@ -330,6 +716,13 @@ fn main() -> Result<()> {
                 dict[idx]
             })
         }))
+    } else if let Some(seed) = opts.seed {
+        eprintln!("WARNING: Seeded output is only as secret as the seed.");
+        Box::new(
+            Uniform::from(0..dict.len())
+                .sample_iter(ChaCha20Rng::from_seed(seed))
+                .map(|i| dict[i]),
+        )
     } else {
         Box::new(
             Uniform::from(0..dict.len())
@@ -338,12 +731,48 @@ fn main() -> Result<()> {
         )
     };
 
-    for _ in 0..opts.count {
-        let password = random_words
-            .by_ref()
-            .take(length as usize)
-            .collect::<Vec<&str>>()
-            .join(separator);
+    let mut clipboard = if opts.clipboard {
+        Some(Clipboard::new().wrap_err("Failed to access clipboard")?)
+    } else {
+        None
+    };
+
+    for i in 0..opts.count {
+        let mut attempts: u32 = 0;
+        let password = loop {
+            let candidate = random_words
+                .by_ref()
+                .take(length as usize)
+                .collect::<Vec<&str>>()
+                .join(separator);
+
+            match &opts.require_classes {
+                Some(req) if !CharDistro::count(&candidate).satisfies(req) => {
+                    attempts += 1;
+                    ensure!(
+                        attempts < MAX_REJECTION_ATTEMPTS,
+                        eyre!(
+                            "--require-classes {:?} was not satisfied after {} rejection-sampling attempts; \
+                             the policy is technically feasible but too rare for this dictionary/length",
+                            req, MAX_REJECTION_ATTEMPTS
+                        )
+                    );
+                    continue;
+                }
+                _ => break candidate,
+            }
+        };
+
+        if let Some(clipboard) = clipboard.as_mut() {
+            if i + 1 == opts.count {
+                clipboard
+                    .set_text(password)
+                    .wrap_err("Failed to copy password to clipboard")?;
+                eprintln!("Password copied to clipboard.");
+                continue;
+            }
+        }
+
         println!("{}", password);
     }
 